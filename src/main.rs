@@ -43,7 +43,9 @@ fn get_mean_rate_painting_nonogram_board(
         let painted_board: PaintedBoard = PaintedBoard::new_random(rng, size, size, p);
         let mut board: Board = painted_board.into_empty_board();
 
-        board.try_paint();
+        if board.try_paint().is_err() {
+            return Err(());
+        }
 
         sum += board.painted_rate();
 
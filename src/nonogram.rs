@@ -1,24 +1,160 @@
 use std::{
+    collections::HashMap,
     fmt::Debug,
-    ops::{Add, Range},
+    str::FromStr,
+    time::{Duration, Instant},
     vec,
 };
 
 use rand::Rng;
 
-const BLACK_COLOR: &str = "░";
-const WHITE_COLOR: &str = "█";
+const BLACK_COLOR: char = '░';
+const WHITE_COLOR: char = '█';
 const UNKNOWN_COLOR: &str = ".";
 
+/// A descriptive error produced while parsing a puzzle from text.
+#[derive(Debug)]
+pub struct ParseError(String);
+
+impl ParseError {
+    fn new(message: impl Into<String>) -> Self {
+        ParseError(message.into())
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Identifies a color in a `ColorPalette`. `0` is reserved for blank/white cells; any
+/// other value is a distinct paint color.
+pub type ColorId = u8;
+
+/// Maps `ColorId`s to the glyph used to render them, indexed by id (index `0` is blank).
+pub struct ColorPalette {
+    glyphs: Vec<char>,
+}
+
+impl ColorPalette {
+    pub fn new(glyphs: Vec<char>) -> Self {
+        ColorPalette { glyphs }
+    }
+
+    /// Falls back to `?` for a color beyond the palette's range, rather than panicking.
+    pub fn glyph(&self, color: ColorId) -> char {
+        self.glyphs.get(color as usize).copied().unwrap_or('?')
+    }
+}
+
+impl Default for ColorPalette {
+    fn default() -> Self {
+        ColorPalette::new(vec![BLACK_COLOR, WHITE_COLOR, '▓', '▒', '▚', '▞'])
+    }
+}
+
+/// A terminal cell's visual attributes, mirroring how terminal cell buffers track a
+/// character: a basic (0-7) foreground and background color plus bold/dim emphasis.
+#[derive(Clone, Copy)]
+pub struct CellAttr {
+    pub fg: u8,
+    pub bg: u8,
+    pub bold: bool,
+    pub dim: bool,
+}
+
+impl CellAttr {
+    fn sgr(&self) -> String {
+        let mut codes = vec![format!("3{}", self.fg), format!("4{}", self.bg)];
+        if self.bold {
+            codes.push("1".to_string());
+        }
+        if self.dim {
+            codes.push("2".to_string());
+        }
+        format!("\x1b[{}m", codes.join(";"))
+    }
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Configures `render_ansi`: the glyph drawn for blank and for unknown cells, the glyph
+/// palette (same role as `ColorPalette`, Unicode blocks or ASCII), and the `CellAttr`
+/// used to colorize each color id's background.
+pub struct RenderTheme {
+    pub unknown_glyph: char,
+    pub blank_glyph: char,
+    pub palette: ColorPalette,
+    attrs: Vec<CellAttr>,
+}
+
+impl RenderTheme {
+    pub fn new(
+        unknown_glyph: char,
+        blank_glyph: char,
+        palette: ColorPalette,
+        attrs: Vec<CellAttr>,
+    ) -> Self {
+        RenderTheme {
+            unknown_glyph,
+            blank_glyph,
+            palette,
+            attrs,
+        }
+    }
+
+    /// Falls back to plain, unstyled text for a color beyond `attrs`' range, rather than
+    /// panicking.
+    fn attr(&self, color: ColorId) -> CellAttr {
+        self.attrs.get(color as usize).copied().unwrap_or(CellAttr {
+            fg: 7,
+            bg: 0,
+            bold: false,
+            dim: false,
+        })
+    }
+
+    fn glyph(&self, color: ColorId) -> char {
+        if color == 0 {
+            self.blank_glyph
+        } else {
+            self.palette.glyph(color)
+        }
+    }
+}
+
+impl Default for RenderTheme {
+    fn default() -> Self {
+        RenderTheme::new(
+            '.',
+            ' ',
+            ColorPalette::default(),
+            vec![
+                CellAttr { fg: 7, bg: 0, bold: false, dim: false },
+                CellAttr { fg: 0, bg: 7, bold: true, dim: false },
+                CellAttr { fg: 7, bg: 1, bold: true, dim: false },
+                CellAttr { fg: 0, bg: 2, bold: true, dim: false },
+                CellAttr { fg: 0, bg: 3, bold: true, dim: false },
+                CellAttr { fg: 7, bg: 5, bold: true, dim: false },
+            ],
+        )
+    }
+}
+
 pub struct PaintedBoard {
     width: usize,
     height: usize,
-    cells: Vec<bool>,
+    cells: Vec<ColorId>,
 }
 
 impl PaintedBoard {
     pub fn new_random(rng: &mut impl Rng, width: usize, height: usize, p: f64) -> Self {
-        let cells: Vec<bool> = (0..width * height).map(|_| rng.random_bool(p)).collect();
+        let cells: Vec<ColorId> = (0..width * height)
+            .map(|_| rng.random_bool(p) as ColorId)
+            .collect();
         Self {
             width,
             height,
@@ -56,6 +192,8 @@ impl PaintedBoard {
 
 impl Debug for PaintedBoard {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let palette = ColorPalette::default();
+
         for k in 0..self.width * self.height {
             if k % self.width == 0 {
                 if k != 0 {
@@ -63,36 +201,94 @@ impl Debug for PaintedBoard {
                 }
             }
 
-            if self.cells[k] {
-                write!(f, "{}", WHITE_COLOR)?;
-            } else {
-                write!(f, "{}", BLACK_COLOR)?;
-            }
+            write!(f, "{}", palette.glyph(self.cells[k]))?;
         }
         Ok(())
     }
 }
 
+impl PaintedBoard {
+    /// Renders the board as ANSI-colored terminal output: every cell becomes a colored
+    /// background block per `theme`, one line per row.
+    pub fn render_ansi(&self, theme: &RenderTheme) -> String {
+        let mut out = String::new();
+
+        for k in 0..self.width * self.height {
+            if k != 0 && k % self.width == 0 {
+                out.push('\n');
+            }
+
+            let color = self.cells[k];
+            out.push_str(&theme.attr(color).sgr());
+            out.push(theme.glyph(color));
+            out.push_str(ANSI_RESET);
+        }
+
+        out
+    }
+}
+
 pub struct PaintedColumn {
-    cells: Vec<bool>,
+    cells: Vec<ColorId>,
 }
 
 impl PaintedColumn {
     pub fn new_random(rng: &mut impl Rng, p: f64, lenght: usize) -> Self {
-        let cells: Vec<bool> = (0..lenght).map(|_| rng.random_bool(p)).collect();
+        let cells: Vec<ColorId> = (0..lenght).map(|_| rng.random_bool(p) as ColorId).collect();
 
         Self { cells }
     }
 }
 
+#[derive(Clone, PartialEq)]
 pub struct ColumnInfo {
-    info: Vec<usize>,
+    info: Vec<(usize, ColorId)>,
 }
 
 impl ColumnInfo {
-    pub fn new(info: Vec<usize>) -> Self {
+    pub fn new(info: Vec<(usize, ColorId)>) -> Self {
         ColumnInfo { info }
     }
+
+    fn painted_cells(&self) -> usize {
+        self.info.iter().map(|&(lenght, _)| lenght).sum()
+    }
+
+    /// Parses one line of space-separated clue numbers, e.g. `"3 1 2"`. Clues parsed this
+    /// way are single-colored, using color `1`.
+    fn from_clue_line(line: &str, line_length: usize) -> Result<ColumnInfo, ParseError> {
+        let info: Vec<(usize, ColorId)> = line
+            .split_whitespace()
+            .map(|token| {
+                token
+                    .parse::<usize>()
+                    .map_err(|_| ParseError::new(format!("{token:?} is not a clue number")))
+                    .map(|lenght| (lenght, 1))
+            })
+            .collect::<Result<_, _>>()?;
+        // A `0` token is a phantom empty block (e.g. `"0 2"` means the same as `"2"`, and a
+        // bare `"0"` means no blocks at all); drop it rather than treating it as a real run
+        // that needs a mandatory gap against its neighbours.
+        let info: Vec<(usize, ColorId)> = info.into_iter().filter(|&(lenght, _)| lenght > 0).collect();
+
+        if let Some(&(lenght, _)) = info.iter().find(|&&(lenght, _)| lenght > line_length) {
+            return Err(ParseError::new(format!(
+                "clue run of {lenght} does not fit in a line of length {line_length}"
+            )));
+        }
+
+        let mandatory_gaps = (1..info.len())
+            .filter(|&i| info[i].1 == info[i - 1].1)
+            .count();
+        let required: usize = info.iter().map(|&(lenght, _)| lenght).sum::<usize>() + mandatory_gaps;
+        if required > line_length {
+            return Err(ParseError::new(format!(
+                "clue {line:?} needs {required} cells (including mandatory gaps) but the line is only {line_length} long"
+            )));
+        }
+
+        Ok(ColumnInfo { info })
+    }
 }
 
 impl Debug for ColumnInfo {
@@ -103,32 +299,34 @@ impl Debug for ColumnInfo {
 
 impl PaintedColumn {
     pub fn get_info(&self) -> ColumnInfo {
-        let mut state = 0;
+        let mut run: Option<(ColorId, usize)> = None;
         let mut info = vec![];
 
-        for cell in &self.cells {
-            match (state, cell) {
-                (_, true) => {
-                    state += 1;
+        for &cell in &self.cells {
+            match run {
+                Some((color, lenght)) if color == cell => {
+                    run = Some((color, lenght + 1));
                 }
-                (0, false) => {
-                    1;
+                Some((color, lenght)) => {
+                    info.push((lenght, color));
+                    run = (cell != 0).then_some((cell, 1));
                 }
-                (_, false) => {
-                    info.push(state);
-                    state = 0;
+                None if cell == 0 => {}
+                None => {
+                    run = Some((cell, 1));
                 }
             }
         }
 
-        if state != 0 {
-            info.push(state);
+        if let Some((color, lenght)) = run {
+            info.push((lenght, color));
         }
 
         ColumnInfo { info }
     }
 }
 
+#[derive(Clone)]
 pub struct ColumnInfos {
     columns: Vec<ColumnInfo>,
     rows: Vec<ColumnInfo>,
@@ -138,15 +336,83 @@ impl ColumnInfos {
     pub fn new(columns: Vec<ColumnInfo>, rows: Vec<ColumnInfo>) -> Self {
         ColumnInfos { columns, rows }
     }
+
+    /// Parses the common textual nonogram format: a `width height` line, a blank line,
+    /// `height` lines of space-separated row clues, another blank line, then `width`
+    /// lines of column clues.
+    pub fn from_clue_str(input: &str) -> Result<ColumnInfos, ParseError> {
+        let mut blocks = input.trim().split("\n\n");
+
+        let mut dims = blocks
+            .next()
+            .ok_or_else(|| ParseError::new("missing width/height header"))?
+            .split_whitespace();
+        let width: usize = dims
+            .next()
+            .ok_or_else(|| ParseError::new("missing board width"))?
+            .parse()
+            .map_err(|_| ParseError::new("board width is not a number"))?;
+        let height: usize = dims
+            .next()
+            .ok_or_else(|| ParseError::new("missing board height"))?
+            .parse()
+            .map_err(|_| ParseError::new("board height is not a number"))?;
+
+        let row_block = blocks
+            .next()
+            .ok_or_else(|| ParseError::new("missing row clues"))?;
+        let rows = Self::parse_clue_lines(row_block, height, width)?;
+
+        let column_block = blocks
+            .next()
+            .ok_or_else(|| ParseError::new("missing column clues"))?;
+        let columns = Self::parse_clue_lines(column_block, width, height)?;
+
+        let row_sum: usize = rows.iter().map(ColumnInfo::painted_cells).sum();
+        let column_sum: usize = columns.iter().map(ColumnInfo::painted_cells).sum();
+        if row_sum != column_sum {
+            return Err(ParseError::new(format!(
+                "row clues paint {row_sum} cells but column clues paint {column_sum} cells"
+            )));
+        }
+
+        Ok(ColumnInfos::new(columns, rows))
+    }
+
+    fn parse_clue_lines(
+        block: &str,
+        expected_lines: usize,
+        line_length: usize,
+    ) -> Result<Vec<ColumnInfo>, ParseError> {
+        let lines: Vec<&str> = block.lines().collect();
+        if lines.len() != expected_lines {
+            return Err(ParseError::new(format!(
+                "expected {expected_lines} clue lines, found {}",
+                lines.len()
+            )));
+        }
+
+        lines
+            .into_iter()
+            .map(|line| ColumnInfo::from_clue_line(line, line_length))
+            .collect()
+    }
 }
 
+#[derive(Clone)]
 pub struct Board {
     width: usize,
     height: usize,
-    cells: Vec<Option<bool>>,
+    cells: Vec<Option<ColorId>>,
     infos: ColumnInfos,
 }
 
+pub enum SolveResult {
+    Unique(PaintedBoard),
+    Multiple,
+    Unsolvable,
+}
+
 impl From<ColumnInfos> for Board {
     fn from(infos: ColumnInfos) -> Self {
         let width = infos.columns.len();
@@ -161,6 +427,14 @@ impl From<ColumnInfos> for Board {
     }
 }
 
+impl FromStr for Board {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(ColumnInfos::from_clue_str(s)?.into())
+    }
+}
+
 impl PaintedBoard {
     pub fn into_empty_board(&self) -> Board {
         Board {
@@ -174,7 +448,7 @@ impl PaintedBoard {
 
 #[derive(Clone)]
 pub struct Column {
-    cells: Vec<Option<bool>>,
+    cells: Vec<Option<ColorId>>,
 }
 
 impl From<PaintedColumn> for Column {
@@ -186,7 +460,7 @@ impl From<PaintedColumn> for Column {
 }
 
 impl Column {
-    pub fn new(cells: Vec<Option<bool>>) -> Column {
+    pub fn new(cells: Vec<Option<ColorId>>) -> Column {
         Column { cells }
     }
 
@@ -230,7 +504,7 @@ impl Board {
     }
 
     pub fn set_column(&mut self, i: usize, column: Column) {
-        for index in 0..self.width {
+        for index in 0..self.height {
             self.cells[i + index * self.width] = column.cells[index];
         }
     }
@@ -238,6 +512,8 @@ impl Board {
 
 impl Debug for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let palette = ColorPalette::default();
+
         let max_size_rows: usize = self
             .infos
             .rows
@@ -245,7 +521,7 @@ impl Debug for Board {
             .map(|row| {
                 row.info
                     .iter()
-                    .map(|&value| value.ilog10() + 2)
+                    .map(|&(lenght, _)| lenght.ilog10() + 2)
                     .sum::<u32>()
             })
             .max()
@@ -260,7 +536,7 @@ impl Debug for Board {
                 let row = self.infos.rows[k / self.width]
                     .info
                     .iter()
-                    .map(|&id| id.to_string() + " ")
+                    .map(|&(lenght, _)| lenght.to_string() + " ")
                     .collect::<String>();
 
                 write!(f, "{row:>max_size_rows$}", max_size_rows = max_size_rows)?;
@@ -268,21 +544,77 @@ impl Debug for Board {
 
             match self.cells[k] {
                 None => write!(f, "{:}", UNKNOWN_COLOR)?,
-                Some(true) => write!(f, "{:}", WHITE_COLOR)?,
-                Some(false) => write!(f, "{:}", BLACK_COLOR)?,
+                Some(color) => write!(f, "{}", palette.glyph(color))?,
             }
         }
         Ok(())
     }
 }
 
+impl Board {
+    /// Renders the board as ANSI-colored terminal output, with the same row-clue gutter
+    /// layout as the `Debug` impl: filled cells become colored background blocks per
+    /// `theme`, unknown cells are drawn dimmed, and the gutter itself is dimmed too so it
+    /// reads apart from the puzzle.
+    pub fn render_ansi(&self, theme: &RenderTheme) -> String {
+        let max_size_rows: usize = self
+            .infos
+            .rows
+            .iter()
+            .map(|row| {
+                row.info
+                    .iter()
+                    .map(|&(lenght, _)| lenght.ilog10() + 2)
+                    .sum::<u32>()
+            })
+            .max()
+            .unwrap() as usize;
+
+        let mut out = String::new();
+
+        for k in 0..self.width * self.height {
+            if k % self.width == 0 {
+                if k != 0 {
+                    out.push('\n');
+                }
+
+                let row = self.infos.rows[k / self.width]
+                    .info
+                    .iter()
+                    .map(|&(lenght, _)| lenght.to_string() + " ")
+                    .collect::<String>();
+
+                out.push_str("\x1b[2m");
+                out.push_str(&format!("{row:>max_size_rows$}", max_size_rows = max_size_rows));
+                out.push_str(ANSI_RESET);
+            }
+
+            match self.cells[k] {
+                None => {
+                    out.push_str("\x1b[2m");
+                    out.push(theme.unknown_glyph);
+                    out.push_str(ANSI_RESET);
+                }
+                Some(color) => {
+                    out.push_str(&theme.attr(color).sgr());
+                    out.push(theme.glyph(color));
+                    out.push_str(ANSI_RESET);
+                }
+            }
+        }
+
+        out
+    }
+}
+
 impl Debug for Column {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let palette = ColorPalette::default();
+
         for cell in &self.cells {
             match cell {
                 None => write!(f, "{:}", UNKNOWN_COLOR)?,
-                Some(true) => write!(f, "{:}", WHITE_COLOR)?,
-                Some(false) => write!(f, "{:}", BLACK_COLOR)?,
+                Some(color) => write!(f, "{}", palette.glyph(*color))?,
             }
         }
         Ok(())
@@ -290,7 +622,9 @@ impl Debug for Column {
 }
 
 impl Board {
-    pub fn try_paint(&mut self) {
+    /// Runs line-by-line propagation to a fixpoint. Returns `Err(())` as soon as some
+    /// row or column has no arrangement fitting its clue, i.e. the board is contradictory.
+    pub fn try_paint(&mut self) -> Result<(), ()> {
         let mut current_hash = self.width * self.height;
         let mut new_hash = 0;
 
@@ -298,17 +632,18 @@ impl Board {
             current_hash = new_hash;
             for index in 0..self.height {
                 let mut row = self.get_row(index);
-                row = row.try_fit(&self.infos.rows[index]).unwrap();
+                let row = row.try_fit(&self.infos.rows[index]).ok_or(())?;
                 self.set_row(index, row);
             }
 
             for index in 0..self.width {
                 let mut column = self.get_column(index);
-                column = column.try_fit(&self.infos.columns[index]).unwrap();
+                let column = column.try_fit(&self.infos.columns[index]).ok_or(())?;
                 self.set_column(index, column);
             }
             new_hash = self.cells.iter().filter(|&cell| cell.is_some()).count();
         }
+        Ok(())
     }
 
     pub fn painted_rate(&self) -> f32 {
@@ -324,18 +659,216 @@ impl Board {
         }
         return true;
     }
+
+    fn is_complete(&self) -> bool {
+        self.cells.iter().all(Option::is_some)
+    }
+
+    fn to_painted_board(&self) -> PaintedBoard {
+        PaintedBoard {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(|&cell| cell.unwrap_or(0)).collect(),
+        }
+    }
+
+    /// Index of an unknown cell picked from the most-constrained line, i.e. the row or
+    /// column with the fewest remaining unknown cells. Returns `None` once every cell
+    /// is painted.
+    fn most_constrained_cell(&self) -> Option<usize> {
+        let mut best: Option<(usize, usize)> = None;
+
+        for j in 0..self.height {
+            let row = self.get_row(j);
+            let unknown = row.cells.iter().filter(|cell| cell.is_none()).count();
+            if unknown == 0 {
+                continue;
+            }
+            if best.is_none_or(|(best_unknown, _)| unknown < best_unknown) {
+                let i = row.cells.iter().position(Option::is_none).unwrap();
+                best = Some((unknown, i + j * self.width));
+            }
+        }
+
+        for i in 0..self.width {
+            let column = self.get_column(i);
+            let unknown = column.cells.iter().filter(|cell| cell.is_none()).count();
+            if unknown == 0 {
+                continue;
+            }
+            if best.is_none_or(|(best_unknown, _)| unknown < best_unknown) {
+                let j = column.cells.iter().position(Option::is_none).unwrap();
+                best = Some((unknown, i + j * self.width));
+            }
+        }
+
+        best.map(|(_, index)| index)
+    }
+
+    /// Every color id (including blank, `0`) that appears anywhere in this board's clues,
+    /// i.e. the candidates worth guessing for an unknown cell.
+    fn colors(&self) -> Vec<ColorId> {
+        let mut colors: Vec<ColorId> = self
+            .infos
+            .rows
+            .iter()
+            .chain(self.infos.columns.iter())
+            .flat_map(|info| info.info.iter().map(|&(_, color)| color))
+            .collect();
+        colors.push(0);
+        colors.sort_unstable();
+        colors.dedup();
+        colors
+    }
+
+    /// Completes line propagation with depth-first search, guessing the unknown cell in
+    /// the most-constrained line and backtracking on contradictions. Keeps searching past
+    /// the first solution found to tell a uniquely-determined puzzle from an ambiguous one.
+    pub fn solve(&mut self) -> SolveResult {
+        if self.try_paint().is_err() {
+            return SolveResult::Unsolvable;
+        }
+
+        let Some(cell) = self.most_constrained_cell() else {
+            return SolveResult::Unique(self.to_painted_board());
+        };
+        debug_assert!(!self.is_complete());
+
+        let mut solution = None;
+
+        for guess in self.colors() {
+            let mut branch = self.clone();
+            branch.cells[cell] = Some(guess);
+
+            match branch.solve() {
+                SolveResult::Unsolvable => continue,
+                SolveResult::Multiple => return SolveResult::Multiple,
+                SolveResult::Unique(board) => {
+                    if solution.is_some() {
+                        return SolveResult::Multiple;
+                    }
+                    solution = Some(board);
+                }
+            }
+        }
+
+        solution.map_or(SolveResult::Unsolvable, SolveResult::Unique)
+    }
 }
 
-impl Column {
-    fn full(lenght: usize, value: Option<bool>) -> Self {
-        Column {
-            cells: vec![value; lenght],
+const ANNEALING_INITIAL_TEMPERATURE: f64 = 10.0;
+const ANNEALING_COOLING_RATE: f64 = 0.995;
+
+impl Board {
+    /// Stochastic fallback for instances where propagation stalls and full backtracking is
+    /// too slow: builds a candidate where every row independently satisfies its own clue (so
+    /// row constraints always hold by construction), then repeatedly re-rolls a random row,
+    /// accepting the move if it lowers the energy (the number of columns whose derived
+    /// `ColumnInfo` doesn't match its target) or, with probability `exp(-delta/T)`, even if it
+    /// doesn't, cooling `T` geometrically. Stops once `deadline` passes or energy hits zero.
+    /// Returns the best board seen and its energy, so callers can tell a true solution
+    /// (`energy == 0`) from a best-effort guess.
+    pub fn solve_annealing(&self, rng: &mut impl Rng, time_budget: Duration) -> (PaintedBoard, usize) {
+        let deadline = Instant::now() + time_budget;
+
+        let mut rows: Vec<Vec<ColorId>> = self
+            .infos
+            .rows
+            .iter()
+            .map(|info| Self::sample_row(info, self.width, rng))
+            .collect();
+        let mut energy = self.energy(&rows);
+
+        let mut best_rows = rows.clone();
+        let mut best_energy = energy;
+
+        let mut temperature = ANNEALING_INITIAL_TEMPERATURE;
+        while best_energy > 0 && Instant::now() < deadline {
+            let j = rng.random_range(0..self.height);
+            let candidate = Self::sample_row(&self.infos.rows[j], self.width, rng);
+            let previous = std::mem::replace(&mut rows[j], candidate);
+
+            let new_energy = self.energy(&rows);
+            let delta = new_energy as f64 - energy as f64;
+            let accept = delta <= 0.0 || rng.random::<f64>() < (-delta / temperature).exp();
+
+            if accept {
+                energy = new_energy;
+                if energy < best_energy {
+                    best_energy = energy;
+                    best_rows = rows.clone();
+                }
+            } else {
+                rows[j] = previous;
+            }
+
+            temperature *= ANNEALING_COOLING_RATE;
         }
+
+        let cells = best_rows.into_iter().flatten().collect();
+        (
+            PaintedBoard {
+                width: self.width,
+                height: self.height,
+                cells,
+            },
+            best_energy,
+        )
+    }
+
+    /// Number of columns whose cells, read out of `rows`, derive a `ColumnInfo` different
+    /// from this board's target for that column.
+    fn energy(&self, rows: &[Vec<ColorId>]) -> usize {
+        (0..self.width)
+            .filter(|&i| {
+                let cells = rows.iter().map(|row| row[i]).collect();
+                PaintedColumn { cells }.get_info() != self.infos.columns[i]
+            })
+            .count()
     }
 
-    fn slice(&self, range: Range<usize>) -> Self {
+    /// Samples one random valid arrangement of `info`'s clue in a line of length
+    /// `lenght`: lays out the blocks (with the mandatory single-cell gap between
+    /// same-colored neighbours) back to back, then scatters the remaining slack as extra
+    /// blank cells before, between, and after them. The slack is split at sorted uniform
+    /// cut points, so not every arrangement is equally likely.
+    fn sample_row(info: &ColumnInfo, lenght: usize, rng: &mut impl Rng) -> Vec<ColorId> {
+        let blocks = &info.info;
+        let mandatory_gaps = (1..blocks.len())
+            .filter(|&i| blocks[i].1 == blocks[i - 1].1)
+            .count();
+        let min_required: usize =
+            blocks.iter().map(|&(block_len, _)| block_len).sum::<usize>() + mandatory_gaps;
+        // `ColumnInfo::new` can still be handed an infeasible clue directly (the from_clue_str
+        // parser rejects this, but that's not the only way to build one); saturate instead of
+        // underflowing so an infeasible line degrades to a best-effort layout instead of
+        // panicking (debug) or hanging on a near-usize::MAX range (release).
+        let slack = lenght.saturating_sub(min_required);
+
+        let mut cuts: Vec<usize> = (0..blocks.len()).map(|_| rng.random_range(0..=slack)).collect();
+        cuts.sort_unstable();
+
+        let mut row = Vec::with_capacity(lenght);
+        let mut previous_cut = 0;
+        for (i, &(block_len, color)) in blocks.iter().enumerate() {
+            row.resize(row.len() + (cuts[i] - previous_cut), 0);
+            previous_cut = cuts[i];
+
+            if i > 0 && blocks[i].1 == blocks[i - 1].1 {
+                row.push(0);
+            }
+            row.resize(row.len() + block_len, color);
+        }
+        row.resize(lenght, 0);
+
+        row
+    }
+}
+
+impl Column {
+    fn full(lenght: usize, value: Option<ColorId>) -> Self {
         Column {
-            cells: self.cells[range].iter().cloned().collect(),
+            cells: vec![value; lenght],
         }
     }
 
@@ -347,78 +880,342 @@ impl Column {
             .all(|(a, b)| a.is_none() || b.is_none() || a.unwrap() == b.unwrap())
     }
 
-    fn add_info(self, column: &mut Option<Column>) {
-        if let Some(column) = column {
-            for i in 0..column.cells.len() {
-                if self.cells[i] != column.cells[i] {
-                    column.cells[i] = None;
+    /// Whether cell `p` is blank-compatible, i.e. not known to be painted.
+    fn blank_ok(&self, p: usize) -> bool {
+        matches!(self.cells[p], None | Some(0))
+    }
+
+    /// Whether cell `p` could hold `color`, i.e. it isn't known to be blank or some other color.
+    fn color_ok(&self, p: usize, color: ColorId) -> bool {
+        self.cells[p].is_none() || self.cells[p] == Some(color)
+    }
+
+    /// For each color that appears in `blocks`, the earliest start (resp. latest end) of a
+    /// `color`-compatible run ending at (resp. starting at) every position, so later window
+    /// checks are O(1) instead of re-scanning the run each time.
+    fn color_reach(&self, blocks: &[(usize, ColorId)]) -> HashMap<ColorId, (Vec<usize>, Vec<usize>)> {
+        let n = self.cells.len();
+        let mut reach = HashMap::new();
+
+        for &(_, color) in blocks {
+            reach.entry(color).or_insert_with(|| {
+                let mut from = vec![0; n + 1];
+                for i in 1..=n {
+                    from[i] = if self.color_ok(i - 1, color) { from[i - 1] } else { i };
                 }
-            }
-        } else {
-            *column = Some(self.clone());
+
+                let mut to = vec![n; n + 1];
+                for j in (0..n).rev() {
+                    to[j] = if self.color_ok(j, color) { to[j + 1] } else { j + 1 };
+                }
+
+                (from, to)
+            });
         }
+
+        reach
     }
 
+    /// Replaces the naive cubic search with the overlap algorithm: a forward and a backward
+    /// boolean DP table (`fits_prefix[i][b]` = "blocks `0..b` fit within `cells[0..i]`",
+    /// `fits_suffix[j][b]` = "blocks `b..` fit within `cells[j..n]`") tell, per block, every start
+    /// consistent with both passes. A cell covered by every feasible start of a block is forced
+    /// filled; a cell covered by none of them, for any block, is forced blank.
     pub fn try_fit(&mut self, info: &ColumnInfo) -> Option<Column> {
-        if info.info.len() == 0 {
-            return Some(Column::full(self.cells.len(), Some(false)));
+        let n = self.cells.len();
+        let blocks = &info.info;
+        let block_count = blocks.len();
+
+        if block_count == 0 {
+            return (0..n)
+                .all(|p| self.blank_ok(p))
+                .then(|| Column::full(n, Some(0)));
+        }
+        if blocks.iter().any(|&(lenght, _)| lenght > n) {
+            return None;
+        }
+
+        let reach = self.color_reach(blocks);
+        let window_fits = |s: usize, e: usize, color: ColorId| {
+            let (from, to) = &reach[&color];
+            s >= from[e] && e <= to[s]
+        };
+        let gap_before = |b: usize| usize::from(b > 0 && blocks[b].1 == blocks[b - 1].1);
+        let gap_after =
+            |b: usize| usize::from(b + 1 < block_count && blocks[b + 1].1 == blocks[b].1);
+
+        // fits_prefix[i][b]: the first `b` blocks fit within the prefix `cells[0..i]`.
+        let mut fits_prefix = vec![vec![false; block_count + 1]; n + 1];
+        fits_prefix[0][0] = true;
+        for i in 1..=n {
+            fits_prefix[i][0] = fits_prefix[i - 1][0] && self.blank_ok(i - 1);
+            for b in 1..=block_count {
+                let (lenght, color) = blocks[b - 1];
+                fits_prefix[i][b] = fits_prefix[i - 1][b] && self.blank_ok(i - 1);
+                if !fits_prefix[i][b] && i >= lenght {
+                    let s = i - lenght;
+                    fits_prefix[i][b] = window_fits(s, i, color)
+                        && if gap_before(b - 1) == 1 {
+                            s >= 1 && self.blank_ok(s - 1) && fits_prefix[s - 1][b - 1]
+                        } else {
+                            fits_prefix[s][b - 1]
+                        };
+                }
+            }
         }
 
-        let mut pn: Vec<Option<Column>> = (0..self.cells.len()).map(|_| None).collect();
+        // fits_suffix[j][b]: blocks `b..` fit within the suffix `cells[j..n]`.
+        let mut fits_suffix = vec![vec![false; block_count + 1]; n + 1];
+        fits_suffix[n][block_count] = true;
+        for j in (0..n).rev() {
+            fits_suffix[j][block_count] = fits_suffix[j + 1][block_count] && self.blank_ok(j);
+            for b in (0..block_count).rev() {
+                let (lenght, color) = blocks[b];
+                fits_suffix[j][b] = fits_suffix[j + 1][b] && self.blank_ok(j);
+                if !fits_suffix[j][b] && j + lenght <= n {
+                    let e = j + lenght;
+                    fits_suffix[j][b] = window_fits(j, e, color)
+                        && if gap_after(b) == 1 {
+                            e < n && self.blank_ok(e) && fits_suffix[e + 1][b + 1]
+                        } else {
+                            fits_suffix[e][b + 1]
+                        };
+                }
+            }
+        }
 
-        let num = info.info[0];
-        for j in num - 1..self.cells.len() {
-            let mut final_column = None;
+        // Known cells carry over as-is; only the gaps the loop below deduces are new information.
+        let mut cells = self.cells.clone();
+        let mut possible = vec![false; n];
+        for b in 0..block_count {
+            let (lenght, color) = blocks[b];
+            let needs_gap_before = gap_before(b) == 1;
+            let needs_gap_after = gap_after(b) == 1;
+
+            // `leftmost`/`rightmost` bound every start this block could take; their overlap
+            // `[rightmost, leftmost + lenght)` is forced-filled below. But known cells can split
+            // the valid starts into disjoint groups, so which cells they *could* cover isn't
+            // just that same range — every start has to be accounted for individually.
+            let is_valid_start = |s: usize| {
+                let e = s + lenght;
+                window_fits(s, e, color)
+                    && if needs_gap_before {
+                        s >= 1 && self.blank_ok(s - 1) && fits_prefix[s - 1][b]
+                    } else {
+                        fits_prefix[s][b]
+                    }
+                    && if needs_gap_after {
+                        e < n && self.blank_ok(e) && fits_suffix[e + 1][b + 1]
+                    } else {
+                        fits_suffix[e][b + 1]
+                    }
+            };
 
-            for k in 0..=j + 1 - num {
-                let column = Column::full(j + 1 - k - num, Some(false))
-                    + Column::full(num, Some(true))
-                    + Column::full(k, Some(false));
+            // A difference array turns "mark every covered cell of every valid start" into O(1)
+            // work per start: `coverage[s] += 1, coverage[s + lenght] -= 1`, then one running sum
+            // below recovers, for each cell, how many valid starts cover it.
+            let mut leftmost = None;
+            let mut rightmost = 0;
+            let mut coverage = vec![0i32; n + 1];
+            for s in (0..=n - lenght).filter(|&s| is_valid_start(s)) {
+                leftmost.get_or_insert(s);
+                rightmost = s;
+                coverage[s] += 1;
+                coverage[s + lenght] -= 1;
+            }
+            let leftmost = leftmost?;
 
-                if column.fit_in(&self.slice(0..j + 1)) {
-                    column.add_info(&mut final_column);
-                }
+            let mut covered = 0;
+            for (p, possible) in possible.iter_mut().enumerate() {
+                covered += coverage[p];
+                *possible |= covered > 0;
+            }
+
+            if rightmost < leftmost + lenght {
+                cells[rightmost..leftmost + lenght].fill(Some(color));
             }
+        }
+        for p in 0..n {
+            if !possible[p] {
+                cells[p] = Some(0);
+            }
+        }
+
+        Some(Column { cells })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn set_column_handles_rectangular_boards() {
+        let mut board: Board = "3 2\n\n3\n3\n\n2\n2\n2".parse().unwrap();
+        assert!(board.try_paint().is_ok());
+        assert_eq!(
+            board.get_row(0).cells,
+            vec![Some(1), Some(1), Some(1)]
+        );
+        assert_eq!(
+            board.get_row(1).cells,
+            vec![Some(1), Some(1), Some(1)]
+        );
+    }
 
-            pn[j] = final_column;
+    #[test]
+    fn solve_finds_the_unique_solution() {
+        let original: Board = "3 3\n\n1\n3\n1\n\n1\n3\n1".parse().unwrap();
+        let mut board = original.clone();
+        match board.solve() {
+            SolveResult::Unique(painted) => {
+                assert!(original.verify(painted));
+            }
+            _ => panic!("expected a unique solution"),
         }
+    }
 
-        let mut space = info.info[0] - 1;
-        for i in 1..info.info.len() {
-            let num = info.info[i];
-            space += num + 1;
+    #[test]
+    fn solve_reports_multiple_solutions() {
+        // Each row and column has exactly one painted cell: the diagonal and the
+        // anti-diagonal both satisfy every clue.
+        let mut board: Board = "2 2\n\n1\n1\n\n1\n1".parse().unwrap();
+        assert!(matches!(board.solve(), SolveResult::Multiple));
+    }
 
-            for j in (space..self.cells.len()).rev() {
-                let mut final_column = None;
+    #[test]
+    fn solve_reports_unsolvable_contradictions() {
+        // Row 1 and column 1 both demand every cell painted, but row 0 and column 0
+        // both demand every cell blank: cell (0, 1) can't satisfy both.
+        let mut board: Board = "2 2\n\n\n2\n\n\n2".parse().unwrap();
+        assert!(matches!(board.solve(), SolveResult::Unsolvable));
+    }
 
-                for k in 0..=j - space {
-                    let Some(others_column) = pn[j - num - k - 1].clone() else {
-                        continue;
-                    };
-                    let column = others_column
-                        + Column::full(1, Some(false))
-                        + Column::full(num, Some(true))
-                        + Column::full(k, Some(false));
+    #[test]
+    fn try_fit_places_differently_colored_adjacent_blocks_without_a_gap() {
+        let info = ColumnInfo::new(vec![(2, 1), (2, 2)]);
+        let mut column = Column::new(vec![None; 4]);
+        let fitted = column.try_fit(&info).unwrap();
+        assert_eq!(fitted.cells, vec![Some(1), Some(1), Some(2), Some(2)]);
+    }
 
-                    if column.fit_in(&self.slice(0..j + 1)) {
-                        column.add_info(&mut final_column);
-                    }
-                }
+    #[test]
+    fn try_fit_requires_a_gap_between_same_colored_adjacent_blocks() {
+        let info = ColumnInfo::new(vec![(2, 1), (2, 1)]);
+        let mut column = Column::new(vec![None; 5]);
+        let fitted = column.try_fit(&info).unwrap();
+        assert_eq!(
+            fitted.cells,
+            vec![Some(1), Some(1), Some(0), Some(1), Some(1)]
+        );
+    }
 
-                pn[j] = final_column;
-            }
+    #[test]
+    fn from_clue_str_parses_a_valid_puzzle() {
+        let infos = ColumnInfos::from_clue_str("3 2\n\n3\n3\n\n2\n2\n2").unwrap();
+        assert_eq!(infos.rows.len(), 2);
+        assert_eq!(infos.columns.len(), 3);
+    }
+
+    #[test]
+    fn from_clue_str_rejects_a_clue_whose_mandatory_gaps_overflow_the_line() {
+        // "3 3" needs 3 + 1 (mandatory gap between same-colored blocks) + 3 = 7 cells,
+        // but the line is only 5 long, even though each individual run fits alone.
+        match ColumnInfos::from_clue_str("5 2\n\n3 3\n1\n\n2\n2\n2\n1\n0") {
+            Err(err) => assert!(err.to_string().contains("needs 7 cells")),
+            Ok(_) => panic!("expected the infeasible clue to be rejected"),
         }
+    }
 
-        pn[self.cells.len() - 1].clone()
+    #[test]
+    fn from_clue_line_treats_a_leading_zero_as_a_phantom_empty_block() {
+        let with_zero = ColumnInfo::from_clue_line("0 2", 2).unwrap();
+        let without_zero = ColumnInfo::from_clue_line("2", 2).unwrap();
+        assert_eq!(with_zero.info, without_zero.info);
+    }
+
+    #[test]
+    fn try_fit_forces_only_the_overlap_of_every_valid_start() {
+        // A block of 4 in a line of 5 can start at 0 or 1; only cells 1..=3 are covered
+        // by both starts, so only those are forced filled.
+        let info = ColumnInfo::new(vec![(4, 1)]);
+        let mut column = Column::new(vec![None; 5]);
+        let fitted = column.try_fit(&info).unwrap();
+        assert_eq!(
+            fitted.cells,
+            vec![None, Some(1), Some(1), Some(1), None]
+        );
     }
-}
 
-impl Add for Column {
-    type Output = Column;
+    #[test]
+    fn try_fit_preserves_already_known_cells() {
+        // Cell 0 is already known blank; the block can only start at 1, so try_fit should
+        // additionally resolve the rest of the line instead of forgetting cell 0.
+        let info = ColumnInfo::new(vec![(4, 1)]);
+        let mut column = Column::new(vec![Some(0), None, None, None, None]);
+        let fitted = column.try_fit(&info).unwrap();
+        assert_eq!(
+            fitted.cells,
+            vec![Some(0), Some(1), Some(1), Some(1), Some(1)]
+        );
+    }
 
-    fn add(self, rhs: Self) -> Self::Output {
-        let cells = [self.cells.as_slice(), rhs.cells.as_slice()].concat();
+    #[test]
+    fn try_fit_rejects_an_empty_clue_against_a_known_non_blank_cell() {
+        let info = ColumnInfo::new(vec![]);
+        let mut column = Column::new(vec![Some(2)]);
+        assert!(column.try_fit(&info).is_none());
+    }
 
-        Column { cells }
+    #[test]
+    fn painted_board_render_ansi_colors_each_cell() {
+        let board = PaintedBoard {
+            width: 2,
+            height: 1,
+            cells: vec![1, 0],
+        };
+        let rendered = board.render_ansi(&RenderTheme::default());
+        assert_eq!(rendered, "\x1b[30;47;1m█\x1b[0m\x1b[37;40m \x1b[0m");
+    }
+
+    #[test]
+    fn board_render_ansi_dims_unknown_cells() {
+        let board: Board = "1 1\n\n1\n\n1".parse().unwrap();
+        let rendered = board.render_ansi(&RenderTheme::default());
+        assert_eq!(rendered, "\x1b[2m1 \x1b[0m\x1b[2m.\x1b[0m");
+    }
+
+    #[test]
+    fn render_ansi_falls_back_for_a_color_beyond_the_theme() {
+        // RenderTheme::default() only covers colors 0..=5; color 6 must degrade to a
+        // fallback glyph/style instead of panicking.
+        let board = PaintedBoard {
+            width: 1,
+            height: 1,
+            cells: vec![6],
+        };
+        let rendered = board.render_ansi(&RenderTheme::default());
+        assert_eq!(rendered, "\x1b[37;40m?\x1b[0m");
+    }
+
+    #[test]
+    fn solve_annealing_converges_to_a_true_solution() {
+        let board: Board = "3 3\n\n1\n3\n1\n\n1\n3\n1".parse().unwrap();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let (painted, energy) =
+            board.solve_annealing(&mut rng, std::time::Duration::from_millis(200));
+        assert_eq!(energy, 0);
+        assert!(board.verify(painted));
+    }
+
+    #[test]
+    fn sample_row_does_not_panic_on_an_infeasible_clue() {
+        // Two blocks of 3 with a mandatory gap between them need 7 cells, but the line
+        // is only 5 long: min_required > lenght must not underflow the slack.
+        let info = ColumnInfo::new(vec![(3, 1), (3, 1)]);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let row = Board::sample_row(&info, 5, &mut rng);
+        assert_eq!(row.len(), 5);
     }
 }